@@ -9,6 +9,34 @@ mod stats_workload;
 
 mod workload;
 
+/// Builds a workload from its context and number of iterations
+type WorkloadConstructor = fn(WorkloadContext, usize) -> WrappedWorkload;
+
+/// Workloads provided by this library, keyed by the `workloadName` of the test file.
+///
+/// Each `[[test.workload]]` entry of a test file is created independently, so
+/// listing several of them runs the workloads concurrently on the same cluster.
+const WORKLOADS: &[(&str, WorkloadConstructor)] =
+    &[(stats_workload::STATS_WORKLOAD_NAME, create_stats_workload)];
+
+/// Creates the statistics workload
+fn create_stats_workload(context: WorkloadContext, iterations: usize) -> WrappedWorkload {
+    let stat_workload = StatsWorkload::new(&context);
+    WrappedWorkload::new(Workload::new(context, iterations, stat_workload))
+}
+
+/// Looks up the entry registered under the given workload name
+///
+/// # Arguments
+/// * `workloads` - Table of workload names and their entries, such as [`WORKLOADS`]
+/// * `name` - Workload name to look up
+fn find_workload<T: Copy>(workloads: &[(&str, T)], name: &str) -> Option<T> {
+    workloads
+        .iter()
+        .find(|(registered, _)| *registered == name)
+        .map(|(_, constructor)| *constructor)
+}
+
 struct CabinetSimulationFactory;
 
 impl RustWorkloadFactory for CabinetSimulationFactory {
@@ -16,14 +44,36 @@ impl RustWorkloadFactory for CabinetSimulationFactory {
         let iteration = context
             .get_option("iterations")
             .expect("Iteration option not found");
-        match name.as_str() {
-            stats_workload::STATS_WORKLOAD_NAME => {
-                let stat_workload = StatsWorkload::new(&context);
-                WrappedWorkload::new(Workload::new(context, iteration, stat_workload))
-            }
-            _ => panic!("Unknown workload: {}", name),
-        }
+        let constructor =
+            find_workload(WORKLOADS, &name).unwrap_or_else(|| panic!("Unknown workload: {}", name));
+        constructor(context, iteration)
     }
 }
 
 register_factory!(CabinetSimulationFactory);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_workloads_resolve() {
+        let workloads = [("FirstWorkload", 1), ("SecondWorkload", 2)];
+
+        assert_eq!(find_workload(&workloads, "FirstWorkload"), Some(1));
+        assert_eq!(find_workload(&workloads, "SecondWorkload"), Some(2));
+    }
+
+    #[test]
+    fn unknown_workload_is_not_found() {
+        let workloads = [("FirstWorkload", 1), ("SecondWorkload", 2)];
+
+        assert_eq!(find_workload(&workloads, "ThirdWorkload"), None);
+        assert_eq!(find_workload(&workloads, "firstworkload"), None);
+    }
+
+    #[test]
+    fn stats_workload_is_registered() {
+        assert!(find_workload(WORKLOADS, stats_workload::STATS_WORKLOAD_NAME).is_some());
+    }
+}
@@ -1,15 +1,14 @@
 /// This module implements a workload for testing cabinet statistics functionality.
 use crate::stats_workload::errors::StatsError;
-use crate::stats_workload::wal::{StatsHolder, Wal};
+use crate::stats_workload::wal::{StatsHolder, Wal, DEFAULT_OVERWRITE_PROBABILITY};
 use crate::workload::WorkloadLogic;
 use rand::{rng, Rng};
 use rand_chacha::rand_core::SeedableRng;
 use toolbox::foundationdb::Database;
 use toolbox::foundationdb::FdbBindingError;
-use toolbox::foundationdb_simulation::WorkloadContext;
+use toolbox::foundationdb_simulation::{Metric, Metrics, Severity, WorkloadContext};
 use toolbox::with_tenant;
 
-
 mod errors;
 mod wal;
 
@@ -22,6 +21,14 @@ pub struct StatsWorkload {
     wal: Wal,
     /// Holder for maintaining statistics
     stats_holder: StatsHolder,
+    /// Number of iterations between two mid-run verifications, 0 to only verify at the end
+    verify_interval: usize,
+    /// Whether put items store a checksum of their value, verified when read back
+    checksum: bool,
+    /// Number of simulated iterations so far
+    iteration: usize,
+    /// Iteration at which the database first diverged from the model, if it did
+    first_divergence: Option<usize>,
 }
 
 impl StatsWorkload {
@@ -30,15 +37,43 @@ impl StatsWorkload {
     /// # Arguments
     /// * `workload_context` - Context containing workload execution parameters
     pub fn new(workload_context: &WorkloadContext) -> Self {
-        let seed =
-            workload_context.shared_random_number() as u64 + workload_context.client_id() as u64;
+        let client_id = workload_context.client_id();
+        let base_seed = workload_context
+            .get_option("seed")
+            .unwrap_or_else(|| workload_context.shared_random_number() as u64);
+        let seed = base_seed.wrapping_add(client_id as u64);
+
+        workload_context.trace(
+            Severity::Info,
+            format!("{} seeded", STATS_WORKLOAD_NAME),
+            &[
+                ("Layer", "Rust"),
+                ("Phase", "Setup"),
+                ("ClientId", &format!("{}", client_id)),
+                ("BaseSeed", &format!("{}", base_seed)),
+                ("Seed", &format!("{}", seed)),
+            ],
+        );
+
         let rng = rand_chacha::ChaCha20Rng::seed_from_u64(seed);
 
-        let wal = Wal::new(rng);
+        let overwrite_probability = workload_context
+            .get_option("overwrite_probability")
+            .unwrap_or(DEFAULT_OVERWRITE_PROBABILITY);
+
+        let wal = Wal::new(rng, overwrite_probability);
+
+        let verify_interval = workload_context.get_option("verify_interval").unwrap_or(0);
+
+        let checksum = workload_context.get_option("checksum").unwrap_or(false);
 
         Self {
             wal,
             stats_holder: Default::default(),
+            verify_interval,
+            checksum,
+            iteration: 0,
+            first_divergence: None,
         }
     }
 
@@ -49,6 +84,103 @@ impl StatsWorkload {
     fn get_tenant(&self, ctx: &WorkloadContext) -> String {
         format!("tenant{}", ctx.client_id())
     }
+
+    /// Compares the database statistics against the model
+    ///
+    /// # Arguments
+    /// * `actual_count` - Item count read from the database
+    /// * `actual_size` - Total size read from the database
+    fn compare_stats(&self, actual_count: i64, actual_size: i64) -> Result<(), StatsError> {
+        let expected_count = self.stats_holder.get_count() as i64;
+        let expected_size = self.stats_holder.get_size() as i64;
+
+        if actual_size != expected_size {
+            return Err(StatsError::InvalidDatabaseStatsSize {
+                actual: actual_size,
+                expected: expected_size,
+            });
+        }
+
+        if actual_count != expected_count {
+            return Err(StatsError::InvalidDatabaseStatsCount {
+                actual: actual_count,
+                expected: expected_count,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Compares the database statistics against the model during the run,
+    /// tracing the first iteration where they diverge
+    ///
+    /// Failing to read the statistics is traced and otherwise ignored, so that
+    /// the diagnostic never changes the outcome of the iteration.
+    ///
+    /// # Arguments
+    /// * `db` - Database instance
+    /// * `ctx` - Workload context
+    /// * `tenant` - Tenant to verify
+    /// * `iteration` - Iteration that has just been simulated
+    async fn verify_incrementally(
+        &mut self,
+        db: &Database,
+        ctx: &WorkloadContext,
+        tenant: &str,
+        iteration: usize,
+    ) {
+        if self.first_divergence.is_some() {
+            return;
+        }
+
+        let (actual_count, actual_size) = match read_stats(db, tenant).await {
+            Ok(stats) => stats,
+            Err(err) => {
+                ctx.trace(
+                    Severity::Warn,
+                    format!("{} unable to read stats", STATS_WORKLOAD_NAME),
+                    &[
+                        ("Layer", "Rust"),
+                        ("Phase", "Start"),
+                        ("Iteration", &format!("{}", iteration)),
+                        ("Error", &err.to_string()),
+                    ],
+                );
+                return;
+            }
+        };
+
+        if let Err(err) = self.compare_stats(actual_count, actual_size) {
+            self.first_divergence = Some(iteration);
+            ctx.trace(
+                Severity::Error,
+                format!("{} diverged from the model", STATS_WORKLOAD_NAME),
+                &[
+                    ("Layer", "Rust"),
+                    ("Phase", "Start"),
+                    ("Iteration", &format!("{}", iteration)),
+                    ("Error", &err.to_string()),
+                ],
+            );
+        }
+    }
+}
+
+/// Reads the item count and total size of a tenant in a single transaction
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `tenant` - Tenant to read statistics from
+async fn read_stats(db: &Database, tenant: &str) -> Result<(i64, i64), FdbBindingError> {
+    with_tenant(db, tenant, |cabinet| async move {
+        let stats = cabinet.get_stats();
+
+        let count = stats.get_count().await?;
+        let size = stats.get_size().await?;
+
+        Ok((count, size))
+    })
+    .await
 }
 
 impl WorkloadLogic for StatsWorkload {
@@ -75,42 +207,18 @@ impl WorkloadLogic for StatsWorkload {
         db: &Database,
         ctx: &WorkloadContext,
     ) -> Result<(), FdbBindingError> {
-        let expected_count = self.stats_holder.get_count() as i64;
-        let expected_size = self.stats_holder.get_size() as i64;
         let tenant = self.get_tenant(ctx);
 
         println!("Check for tenant {tenant}");
 
-        with_tenant(db, &tenant, |cabinet| async move {
-            let stats = cabinet.get_stats();
-
-            let mut actual_count = stats.get_count().await?;
-            let actual_size = stats.get_size().await?;
-
-            let mut rng = rng();
-            if rng.random_bool(0.01) {
-                actual_count = 1;
-            }
-
-            if actual_size != expected_size {
-                return Err(StatsError::InvalidDatabaseStatsSize {
-                    actual: actual_size,
-                    expected: expected_size,
-                }
-                .into());
-            }
+        let (mut actual_count, actual_size) = read_stats(db, &tenant).await?;
 
-            if actual_count != expected_count {
-                return Err(StatsError::InvalidDatabaseStatsCount {
-                    actual: actual_count,
-                    expected: expected_count,
-                }
-                .into());
-            }
+        let mut rng = rng();
+        if rng.random_bool(0.01) {
+            actual_count = 1;
+        }
 
-            Ok(())
-        })
-        .await?;
+        self.compare_stats(actual_count, actual_size)?;
 
         Ok(())
     }
@@ -125,13 +233,17 @@ impl WorkloadLogic for StatsWorkload {
         db: &Database,
         ctx: &WorkloadContext,
     ) -> Result<(), FdbBindingError> {
+        let iteration = self.iteration;
+        self.iteration += 1;
+
         let tenant = self.get_tenant(ctx);
         let event = self.wal.next_event(&tenant);
+        let checksum = self.checksum;
 
         println!("{tenant} => {:?}", event);
 
         let result = with_tenant(&db, &tenant, |cabinet| async move {
-            let result = event.apply(cabinet).await;
+            let result = event.apply(cabinet, checksum).await;
             if let Err(err) = &result {
                 println!("***************------*****{err}");
             }
@@ -143,9 +255,16 @@ impl WorkloadLogic for StatsWorkload {
             println!("/////////////////{err:?}");
         }
 
-        let result = result?;
+        if let Ok(result) = &result {
+            result.update_stats(&mut self.stats_holder);
+        }
+
+        // Verify even when the iteration failed, so that the interval is kept
+        if self.verify_interval > 0 && (iteration + 1) % self.verify_interval == 0 {
+            self.verify_incrementally(db, ctx, &tenant, iteration).await;
+        }
 
-        result.update_stats(&mut self.stats_holder);
+        result?;
 
         Ok(())
     }
@@ -154,4 +273,14 @@ impl WorkloadLogic for StatsWorkload {
     fn name(&self) -> &'static str {
         STATS_WORKLOAD_NAME
     }
+
+    /// Reports the iteration of the first mid-run divergence, if any
+    ///
+    /// # Arguments
+    /// * `out` - Metrics collection to add to
+    fn extra_metrics(&self, out: &mut Metrics) {
+        if let Some(iteration) = self.first_divergence {
+            out.push(Metric::val("first_divergence_iteration", iteration as f64));
+        }
+    }
 }
@@ -47,8 +47,13 @@ pub enum WalEvent {
 /// Result of applying a WAL event
 #[derive(Debug)]
 pub enum ApplyResult {
-    /// Result of Put operation with the stored Item
-    Put(Item),
+    /// Result of Put operation with the stored Item and the Item it replaced, if any
+    Put {
+        /// Item that was stored
+        item: Item,
+        /// Item previously stored under the same key
+        previous: Option<Item>,
+    },
     /// Result of Delete operation with optional removed Item
     Delete(Option<Item>),
     /// Result of Clear operation
@@ -60,15 +65,24 @@ impl WalEvent {
     ///
     /// # Parameters
     /// * `cabinet` - Cabinet instance to apply the event to
+    /// * `checksum` - Whether put items store a checksum of their value
     ///
     /// # Returns
     /// Result containing the ApplyResult or an error
-    pub async fn apply(&self, cabinet: Tenant) -> cabinet::errors::Result<ApplyResult> {
+    pub async fn apply(
+        &self,
+        cabinet: Tenant,
+        checksum: bool,
+    ) -> cabinet::errors::Result<ApplyResult> {
         match self {
             WalEvent::Put { key, value } => {
-                let item = Item::new(&key, &value);
+                let previous = cabinet.get::<Item>(&key).await?;
+                let mut item = Item::new(&key, &value);
+                if checksum {
+                    item = item.with_checksum();
+                }
                 cabinet.put(&item).await?;
-                Ok(ApplyResult::Put(item))
+                Ok(ApplyResult::Put { item, previous })
             }
             WalEvent::Delete { key } => {
                 if let Some(item) = cabinet.delete(&key).await? {
@@ -91,7 +105,10 @@ impl ApplyResult {
     /// * `stats` - Statistics holder to update
     pub fn update_stats(&self, stats: &mut StatsHolder) {
         match self {
-            ApplyResult::Put(item) => stats.put(item),
+            ApplyResult::Put { item, previous } => match previous {
+                Some(previous) => stats.overwrite(previous, item),
+                None => stats.put(item),
+            },
             ApplyResult::Delete(item) => {
                 if let Some(item) = item {
                     stats.delete(item);
@@ -141,6 +158,9 @@ const EVENT_PROBABILITIES: [f32; EVENT_TYPE_CARDINALITY as usize] = [0.89, 0.1,
 /// Probability of deleting an existing key vs generating a random key
 const DELETION_PROBABILITY: f64 = 0.55;
 
+/// Default probability of a put overwriting an existing key vs generating a new key
+pub const DEFAULT_OVERWRITE_PROBABILITY: f64 = 0.2;
+
 /// Holds statistics about the current state
 #[derive(Debug, Default)]
 pub struct StatsHolder {
@@ -158,6 +178,16 @@ impl StatsHolder {
         self.size += item.as_bytes().expect("Unable to get item bytes").len() as u64;
     }
 
+    /// Updates stats after overwriting an item, the count is left unchanged
+    ///
+    /// # Parameters
+    /// * `previous` - Item that was replaced
+    /// * `item` - Item that was put
+    pub fn overwrite(&mut self, previous: &Item, item: &Item) {
+        self.size -= previous.as_bytes().expect("Unable to get item bytes").len() as u64;
+        self.size += item.as_bytes().expect("Unable to get item bytes").len() as u64;
+    }
+
     /// Updates stats after deleting an item
     ///
     /// # Parameters
@@ -189,6 +219,7 @@ pub struct Wal {
     keys: HashMap<String, Vec<Vec<u8>>>,
     rng: ChaCha20Rng,
     weighted_events: WeightedIndex<f32>,
+    overwrite_probability: f64,
 }
 
 /// Generates a random key using the given RNG
@@ -202,19 +233,37 @@ fn random_key(rng: &mut ChaCha20Rng) -> Vec<u8> {
     key
 }
 
+/// Generates a random value using the given RNG
+///
+/// # Parameters
+/// * `rng` - RNG to use for value generation
+fn random_value(rng: &mut ChaCha20Rng) -> Vec<u8> {
+    let value_length = rng.random_range(MIN_VALUE_LENGTH..MAX_VALUE_LENGTH);
+    let mut value = vec![0; value_length as usize];
+    rng.fill_bytes(&mut value);
+    value
+}
+
 impl Wal {
     /// Creates a new WAL with the given RNG
     ///
     /// # Parameters
     /// * `rng` - RNG to use for generating events
-    pub fn new(rng: ChaCha20Rng) -> Self {
+    /// * `overwrite_probability` - Probability of a put targeting an already tracked key
+    pub fn new(rng: ChaCha20Rng, overwrite_probability: f64) -> Self {
         let weighted_events =
             WeightedIndex::new(&EVENT_PROBABILITIES).expect("Failed to create weighted index");
 
+        assert!(
+            (0.0..=1.0).contains(&overwrite_probability),
+            "Overwrite probability must be between 0 and 1, got {overwrite_probability}"
+        );
+
         Self {
             keys: Default::default(),
             rng,
             weighted_events,
+            overwrite_probability,
         }
     }
 
@@ -228,13 +277,15 @@ impl Wal {
 
         match event_type {
             EventType::Put => {
-                let key_length = self.rng.random_range(MIN_KEY_LENGTH..MAX_KEY_LENGTH);
-                let mut key = vec![0; key_length as usize];
-                self.rng.fill_bytes(&mut key);
+                if self.rng.random_bool(self.overwrite_probability) {
+                    if let Some(key) = self.pick_tracked_key(tenant) {
+                        let value = random_value(&mut self.rng);
+                        return WalEvent::Put { key, value };
+                    }
+                }
 
-                let value_length = self.rng.random_range(MIN_VALUE_LENGTH..MAX_VALUE_LENGTH);
-                let mut data = vec![0; value_length as usize];
-                self.rng.fill_bytes(&mut data);
+                let key = random_key(&mut self.rng);
+                let data = random_value(&mut self.rng);
 
                 let event = WalEvent::Put {
                     key: key.clone(),
@@ -272,6 +323,20 @@ impl Wal {
         }
     }
 
+    /// Picks one of the keys already put for a tenant, if any
+    ///
+    /// # Parameters
+    /// * `tenant` - Tenant to pick a key from
+    fn pick_tracked_key(&mut self, tenant: &str) -> Option<Vec<u8>> {
+        let tenant_keys = self.keys.get(tenant)?;
+        if tenant_keys.is_empty() {
+            return None;
+        }
+
+        let index = self.rng.random_range(0..tenant_keys.len());
+        Some(tenant_keys[index].clone())
+    }
+
     /// Generates a random delete event
     fn push_random_delete(&mut self) -> WalEvent {
         let key = random_key(&mut self.rng);
@@ -279,3 +344,55 @@ impl Wal {
         event
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an item the way a checksummed put does, then reads it back
+    fn checksummed_round_trip(key: &[u8], value: &[u8]) -> (Item, Item) {
+        let item = Item::new(key, value).with_checksum();
+        let stored = Item::from_bytes(&item.as_bytes().unwrap()).unwrap();
+        (item, stored)
+    }
+
+    #[test]
+    fn checksummed_overwrite_keeps_stats_in_sync() {
+        let mut stats = StatsHolder::default();
+        let (item, previous) = checksummed_round_trip(b"key", b"value");
+
+        ApplyResult::Put {
+            item,
+            previous: None,
+        }
+        .update_stats(&mut stats);
+
+        let (item, _) = checksummed_round_trip(b"key", b"a longer value");
+        let expected_size = item.as_bytes().unwrap().len() as u64;
+
+        ApplyResult::Put {
+            item,
+            previous: Some(previous),
+        }
+        .update_stats(&mut stats);
+
+        assert_eq!(stats.get_count(), 1);
+        assert_eq!(stats.get_size(), expected_size);
+    }
+
+    #[test]
+    fn checksummed_delete_empties_stats() {
+        let mut stats = StatsHolder::default();
+        let (item, stored) = checksummed_round_trip(b"key", b"value");
+
+        ApplyResult::Put {
+            item,
+            previous: None,
+        }
+        .update_stats(&mut stats);
+        ApplyResult::Delete(Some(stored)).update_stats(&mut stats);
+
+        assert_eq!(stats.get_count(), 0);
+        assert_eq!(stats.get_size(), 0);
+    }
+}
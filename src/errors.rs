@@ -12,6 +12,12 @@ pub enum CabinetError {
     FdbError(#[from] foundationdb::FdbError),
     #[error(transparent)]
     Backend(#[from] BackendError),
+    #[error("Unable to read cluster file {path}: {source}")]
+    ClusterFile {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
 }
 
 impl From<CabinetError> for FdbBindingError {
@@ -20,6 +26,7 @@ impl From<CabinetError> for FdbBindingError {
             CabinetError::FdbBinddingError(e) => e,
             CabinetError::FdbError(e) => FdbBindingError::NonRetryableFdbError(e),
             CabinetError::Backend(err) => err.into(),
+            err @ CabinetError::ClusterFile { .. } => FdbBindingError::CustomError(Box::new(err)),
         }
     }
 }
@@ -5,11 +5,64 @@ use std::fmt::{Debug, Formatter};
 use toolbox::backend::errors::BackendError;
 use toolbox::backend::record::Record;
 
+/// Leading byte of versioned item encodings.
+///
+/// Bincode encodes the key length as a varint whose first byte is at most 254,
+/// so a legacy encoding without prefix can never start with this marker.
+const FORMAT_MARKER: u8 = 0xFF;
+
+/// Length of the format prefix, made of the marker, version and flags bytes
+///
+/// Not to be confused with the metadata headers announced by [`FLAG_HEADERS`].
+const PREFIX_LENGTH: usize = 3;
+
+/// Length of the value checksum trailing the payload
+const CHECKSUM_LENGTH: usize = 4;
+
+/// Flag set when a CRC32 of the value trails the payload
+const FLAG_CHECKSUM: u8 = 0b0000_0001;
+
+/// Flag set when an expiration timestamp follows the key and value
+const FLAG_EXPIRATION: u8 = 0b0000_0010;
+
+/// Flag set when the stored value is zstd compressed, its uncompressed length
+/// following the headers
+const FLAG_COMPRESSED: u8 = 0b0000_0100;
+
+/// Flag set when metadata headers follow the expiration
+const FLAG_HEADERS: u8 = 0b0000_1000;
+
+/// Flags understood by this version of the format
+const KNOWN_FLAGS: u8 = FLAG_CHECKSUM | FLAG_EXPIRATION | FLAG_COMPRESSED | FLAG_HEADERS;
+
+/// Values longer than this are compressed, if it makes them smaller
+const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Largest value that is compressed, which bounds the memory decompression
+/// may allocate for a corrupted length
+const MAX_DECOMPRESSED_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Format version of items stored before the format prefix was introduced
+pub const LEGACY_FORMAT_VERSION: u8 = 0;
+
+/// Format version written by [`Item::as_bytes`]
+///
+/// Items stored in the legacy format re-encode to a different length, the
+/// format prefix alone adding three bytes. Size statistics computed from
+/// [`Record::as_bytes`] therefore drift on delete or overwrite of such items
+/// until the tenant is migrated with [`Item::migrate`].
+pub const CURRENT_FORMAT_VERSION: u8 = 1;
+
 /// Represents a key-value pair item that can be stored in the cabinet.
-#[derive(bincode::Encode, bincode::Decode)]
 pub struct Item {
     key: Vec<u8>,
     pub value: Vec<u8>,
+    /// Whether a checksum of the value is stored alongside it
+    checksum: bool,
+    /// Unix timestamp in milliseconds from which the item is expired
+    expires_at: Option<u64>,
+    /// Metadata annotations stored alongside the value, such as a content type
+    headers: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 impl Debug for Item {
@@ -36,23 +89,280 @@ impl Item {
         Item {
             key: key.to_vec(),
             value: value.to_vec(),
+            checksum: false,
+            expires_at: None,
+            headers: Vec::new(),
+        }
+    }
+
+    /// Stores a CRC32 of the value alongside it, verified when the item is read back.
+    ///
+    /// Items read with a checksum keep it when written again.
+    ///
+    /// # Returns
+    /// This Item with checksums enabled
+    pub fn with_checksum(mut self) -> Item {
+        self.checksum = true;
+        self
+    }
+
+    /// Sets the instant from which the item is expired.
+    ///
+    /// # Parameters
+    /// * `expires_at` - Unix timestamp in milliseconds
+    ///
+    /// # Returns
+    /// This Item with the expiration set
+    pub fn with_expiration(mut self, expires_at: u64) -> Item {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Gets the expiration of this item.
+    ///
+    /// # Returns
+    /// Unix timestamp in milliseconds from which the item is expired, if any
+    pub fn expires_at(&self) -> Option<u64> {
+        self.expires_at
+    }
+
+    /// Checks whether the item is expired at the given instant.
+    ///
+    /// # Parameters
+    /// * `now` - Unix timestamp in milliseconds
+    ///
+    /// # Returns
+    /// `true` if the item has an expiration at or before `now`
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Adds a metadata header to the item.
+    ///
+    /// # Parameters
+    /// * `name` - Byte slice containing the header name
+    /// * `value` - Byte slice containing the header value
+    ///
+    /// # Returns
+    /// This Item with the header appended
+    pub fn with_header(mut self, name: &[u8], value: &[u8]) -> Item {
+        self.headers.push((name.to_vec(), value.to_vec()));
+        self
+    }
+
+    /// Gets the metadata headers of this item, in insertion order.
+    ///
+    /// # Returns
+    /// A slice of header name and value pairs
+    pub fn headers(&self) -> &[(Vec<u8>, Vec<u8>)] {
+        &self.headers
+    }
+
+    /// Detects the format version of a serialized item without decoding it.
+    ///
+    /// # Parameters
+    /// * `bytes` - Serialized bytes of an Item
+    ///
+    /// # Returns
+    /// The format version the bytes were written with
+    pub fn format_version(bytes: &[u8]) -> u8 {
+        match bytes {
+            [FORMAT_MARKER, version, ..] => *version,
+            _ => LEGACY_FORMAT_VERSION,
+        }
+    }
+
+    /// Re-encodes a serialized item written in an older format version.
+    ///
+    /// Running it on bytes already in the current format is a no-op, which
+    /// makes migrating a tenant idempotent. Size statistics of a tenant
+    /// drift for legacy items until they are migrated, callers keeping them
+    /// must adjust them by the difference between the two encodings.
+    ///
+    /// # Parameters
+    /// * `bytes` - Serialized bytes of an Item
+    ///
+    /// # Returns
+    /// The item encoded in the current format, or `None` if it already is
+    pub fn migrate(bytes: &[u8]) -> Result<Option<Vec<u8>>, BackendError> {
+        if Item::format_version(bytes) == CURRENT_FORMAT_VERSION {
+            return Ok(None);
+        }
+
+        let item = Item::from_bytes(bytes)?;
+        Ok(Some(item.as_bytes()?))
+    }
+
+    /// Decodes an item payload and the optional sections announced by its flags.
+    ///
+    /// # Parameters
+    /// * `payload` - Bincode encoded key and value, followed by the flagged sections
+    /// * `flags` - Flags read from the format prefix, 0 for formats without flags
+    ///
+    /// # Returns
+    /// Decoded Item
+    fn decode_payload(payload: &[u8], flags: u8) -> Result<Item, BackendError> {
+        let config = bincode::config::standard();
+        let ((key, value), mut read): ((Vec<u8>, Vec<u8>), usize) =
+            decode_from_slice(payload, config)
+                .map_err(|err| BackendError::DeserializationError(err.to_string()))?;
+
+        let mut item = Item {
+            key,
+            value,
+            checksum: false,
+            expires_at: None,
+            headers: Vec::new(),
+        };
+
+        if flags & FLAG_EXPIRATION != 0 {
+            let (expires_at, length): (u64, usize) = decode_from_slice(&payload[read..], config)
+                .map_err(|err| BackendError::DeserializationError(err.to_string()))?;
+            item.expires_at = Some(expires_at);
+            read += length;
+        }
+
+        if flags & FLAG_HEADERS != 0 {
+            let (headers, length): (Vec<(Vec<u8>, Vec<u8>)>, usize) =
+                decode_from_slice(&payload[read..], config)
+                    .map_err(|err| BackendError::DeserializationError(err.to_string()))?;
+            item.headers = headers;
+            read += length;
+        }
+
+        if flags & FLAG_COMPRESSED != 0 {
+            let (uncompressed_length, length): (u64, usize) =
+                decode_from_slice(&payload[read..], config)
+                    .map_err(|err| BackendError::DeserializationError(err.to_string()))?;
+            read += length;
+            item.decompress_value(uncompressed_length)?;
+        }
+
+        if flags & FLAG_CHECKSUM != 0 {
+            item.verify_checksum(&payload[read..])?;
+        }
+
+        Ok(item)
+    }
+
+    /// Compresses the value if it is large enough and compression pays off.
+    ///
+    /// # Returns
+    /// The compressed value, or `None` if the value should be stored as is
+    fn compress_value(&self) -> Result<Option<Vec<u8>>, BackendError> {
+        if self.value.len() <= COMPRESSION_THRESHOLD || self.value.len() > MAX_DECOMPRESSED_LENGTH {
+            return Ok(None);
+        }
+
+        let compressed = zstd::encode_all(self.value.as_slice(), zstd::DEFAULT_COMPRESSION_LEVEL)
+            .map_err(|err| BackendError::SerialiazationError(err.to_string()))?;
+
+        Ok((compressed.len() < self.value.len()).then_some(compressed))
+    }
+
+    /// Decompresses the stored value into a buffer of its recorded length.
+    ///
+    /// # Parameters
+    /// * `length` - Uncompressed length stored alongside the value
+    fn decompress_value(&mut self, length: u64) -> Result<(), BackendError> {
+        let length = usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= MAX_DECOMPRESSED_LENGTH)
+            .ok_or_else(|| {
+                BackendError::DeserializationError(format!(
+                    "Uncompressed value length {length} exceeds {MAX_DECOMPRESSED_LENGTH}"
+                ))
+            })?;
+
+        let value = zstd::bulk::decompress(&self.value, length)
+            .map_err(|err| BackendError::DeserializationError(err.to_string()))?;
+        if value.len() != length {
+            return Err(BackendError::DeserializationError(format!(
+                "Uncompressed value length mismatch: expected {length}, actual {}",
+                value.len()
+            )));
+        }
+
+        self.value = value;
+        Ok(())
+    }
+
+    /// Checks the value against the checksum stored after it.
+    ///
+    /// # Parameters
+    /// * `trailer` - Bytes following the payload
+    fn verify_checksum(&mut self, trailer: &[u8]) -> Result<(), BackendError> {
+        let expected = <[u8; CHECKSUM_LENGTH]>::try_from(trailer)
+            .map(u32::from_le_bytes)
+            .map_err(|_| BackendError::DeserializationError("Missing item checksum".to_string()))?;
+
+        let actual = crc32fast::hash(&self.value);
+        if actual != expected {
+            return Err(BackendError::DeserializationError(format!(
+                "Checksum mismatch: expected {expected:08x}, actual {actual:08x}"
+            )));
         }
+
+        self.checksum = true;
+        Ok(())
     }
 }
 
 impl Record for Item {
-    /// Serializes this item into bytes.
+    /// Serializes this item into bytes, prefixed by the current format version.
+    ///
+    /// Values above the compression threshold are stored zstd compressed
+    /// when that makes them smaller.
     ///
     /// # Returns
     /// Serialized bytes of this item
     fn as_bytes(&self) -> Result<Vec<u8>, BackendError> {
+        let compressed = self.compress_value()?;
+        let stored_value = compressed.as_deref().unwrap_or(&self.value);
+
         let config = bincode::config::standard();
-        let encoded = encode_to_vec(self, config)
+        let encoded = encode_to_vec((&self.key, stored_value), config)
             .map_err(|err| BackendError::SerialiazationError(err.to_string()))?;
-        Ok(encoded)
+
+        let mut flags = 0;
+        if compressed.is_some() {
+            flags |= FLAG_COMPRESSED;
+        }
+        if self.checksum {
+            flags |= FLAG_CHECKSUM;
+        }
+        if self.expires_at.is_some() {
+            flags |= FLAG_EXPIRATION;
+        }
+        if !self.headers.is_empty() {
+            flags |= FLAG_HEADERS;
+        }
+
+        let mut bytes = Vec::with_capacity(PREFIX_LENGTH + encoded.len() + CHECKSUM_LENGTH);
+        bytes.extend_from_slice(&[FORMAT_MARKER, CURRENT_FORMAT_VERSION, flags]);
+        bytes.extend_from_slice(&encoded);
+        if let Some(expires_at) = self.expires_at {
+            let expiration = encode_to_vec(expires_at, config)
+                .map_err(|err| BackendError::SerialiazationError(err.to_string()))?;
+            bytes.extend_from_slice(&expiration);
+        }
+        if !self.headers.is_empty() {
+            let headers = encode_to_vec(&self.headers, config)
+                .map_err(|err| BackendError::SerialiazationError(err.to_string()))?;
+            bytes.extend_from_slice(&headers);
+        }
+        if compressed.is_some() {
+            let length = encode_to_vec(self.value.len() as u64, config)
+                .map_err(|err| BackendError::SerialiazationError(err.to_string()))?;
+            bytes.extend_from_slice(&length);
+        }
+        if self.checksum {
+            bytes.extend_from_slice(&crc32fast::hash(&self.value).to_le_bytes());
+        }
+        Ok(bytes)
     }
 
-    /// Creates an Item from serialized bytes.
+    /// Creates an Item from serialized bytes, in any supported format version.
     ///
     /// # Parameters
     /// * `bytes` - Serialized bytes of an Item
@@ -60,10 +370,27 @@ impl Record for Item {
     /// # Returns
     /// Deserialized Item
     fn from_bytes(bytes: &[u8]) -> Result<Item, BackendError> {
-        let config = bincode::config::standard();
-        let (item, _) = decode_from_slice(bytes, config)
-            .map_err(|err| BackendError::DeserializationError(err.to_string()))?;
-        Ok(item)
+        match Item::format_version(bytes) {
+            LEGACY_FORMAT_VERSION => Item::decode_payload(bytes, 0),
+            CURRENT_FORMAT_VERSION => {
+                let Some(&flags) = bytes.get(PREFIX_LENGTH - 1) else {
+                    return Err(BackendError::DeserializationError(
+                        "Truncated item prefix".to_string(),
+                    ));
+                };
+
+                if flags & !KNOWN_FLAGS != 0 {
+                    return Err(BackendError::DeserializationError(format!(
+                        "Unsupported item flags {flags:#010b}"
+                    )));
+                }
+
+                Item::decode_payload(&bytes[PREFIX_LENGTH..], flags)
+            }
+            version => Err(BackendError::DeserializationError(format!(
+                "Unsupported item format version {version}"
+            ))),
+        }
     }
 
     /// Gets the key of this item.
@@ -74,3 +401,241 @@ impl Record for Item {
         &self.key
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoding_error(bytes: &[u8]) -> String {
+        match Item::from_bytes(bytes) {
+            Err(BackendError::DeserializationError(message)) => message,
+            other => panic!("Expected a deserialization error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trip_without_checksum() {
+        let bytes = Item::new(b"key", b"value").as_bytes().unwrap();
+        assert_eq!(
+            &bytes[..PREFIX_LENGTH],
+            &[FORMAT_MARKER, CURRENT_FORMAT_VERSION, 0]
+        );
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.get_key(), b"key");
+        assert_eq!(item.value, b"value");
+        assert!(!item.checksum);
+    }
+
+    #[test]
+    fn round_trip_with_checksum() {
+        let bytes = Item::new(b"key", b"value")
+            .with_checksum()
+            .as_bytes()
+            .unwrap();
+        assert_eq!(bytes[PREFIX_LENGTH - 1], FLAG_CHECKSUM);
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.get_key(), b"key");
+        assert_eq!(item.value, b"value");
+        assert!(item.checksum);
+        assert_eq!(item.as_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn flipped_value_byte_fails_checksum() {
+        let mut bytes = Item::new(b"key", b"value")
+            .with_checksum()
+            .as_bytes()
+            .unwrap();
+        let last_value_byte = bytes.len() - CHECKSUM_LENGTH - 1;
+        bytes[last_value_byte] ^= 0x01;
+
+        assert!(decoding_error(&bytes).starts_with("Checksum mismatch"));
+    }
+
+    #[test]
+    fn truncated_checksum_is_missing() {
+        let mut bytes = Item::new(b"key", b"value")
+            .with_checksum()
+            .as_bytes()
+            .unwrap();
+        bytes.pop();
+
+        assert_eq!(decoding_error(&bytes), "Missing item checksum");
+    }
+
+    #[test]
+    fn unknown_flag_is_rejected() {
+        let mut bytes = Item::new(b"key", b"value").as_bytes().unwrap();
+        bytes[PREFIX_LENGTH - 1] |= 0b1000_0000;
+
+        assert!(decoding_error(&bytes).starts_with("Unsupported item flags"));
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let bytes = [FORMAT_MARKER, CURRENT_FORMAT_VERSION + 1, 0];
+
+        assert!(decoding_error(&bytes).starts_with("Unsupported item format version"));
+    }
+
+    #[test]
+    fn expiration_round_trips() {
+        let bytes = Item::new(b"key", b"value")
+            .with_expiration(1_700_000_000_000)
+            .as_bytes()
+            .unwrap();
+        assert_ne!(bytes[PREFIX_LENGTH - 1] & FLAG_EXPIRATION, 0);
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.expires_at(), Some(1_700_000_000_000));
+        assert_eq!(item.value, b"value");
+    }
+
+    #[test]
+    fn no_expiration_leaves_flag_unset() {
+        let bytes = Item::new(b"key", b"value").as_bytes().unwrap();
+        assert_eq!(bytes[PREFIX_LENGTH - 1] & FLAG_EXPIRATION, 0);
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.expires_at(), None);
+        assert!(!item.is_expired(u64::MAX));
+    }
+
+    #[test]
+    fn expired_from_expiration_instant() {
+        let item = Item::new(b"key", b"value").with_expiration(1_000);
+
+        assert!(!item.is_expired(999));
+        assert!(item.is_expired(1_000));
+        assert!(item.is_expired(1_001));
+    }
+
+    /// Generates bytes that zstd cannot compress
+    fn incompressible(length: usize) -> Vec<u8> {
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compressible_value_is_compressed() {
+        let value = vec![b'a'; 4 * COMPRESSION_THRESHOLD];
+        let bytes = Item::new(b"key", &value).as_bytes().unwrap();
+        assert_ne!(bytes[PREFIX_LENGTH - 1] & FLAG_COMPRESSED, 0);
+        assert!(bytes.len() < value.len());
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.value, value);
+    }
+
+    #[test]
+    fn incompressible_value_is_stored_as_is() {
+        let value = incompressible(4 * COMPRESSION_THRESHOLD);
+        let bytes = Item::new(b"key", &value).as_bytes().unwrap();
+        assert_eq!(bytes[PREFIX_LENGTH - 1] & FLAG_COMPRESSED, 0);
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.value, value);
+    }
+
+    #[test]
+    fn small_value_is_not_compressed() {
+        let value = vec![b'a'; COMPRESSION_THRESHOLD];
+        let bytes = Item::new(b"key", &value).as_bytes().unwrap();
+        assert_eq!(bytes[PREFIX_LENGTH - 1] & FLAG_COMPRESSED, 0);
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.value, value);
+    }
+
+    #[test]
+    fn oversized_uncompressed_length_is_rejected() {
+        let value = vec![b'a'; 4 * COMPRESSION_THRESHOLD];
+        let compressed = zstd::encode_all(value.as_slice(), 0).unwrap();
+        let config = bincode::config::standard();
+
+        let mut bytes = vec![FORMAT_MARKER, CURRENT_FORMAT_VERSION, FLAG_COMPRESSED];
+        bytes.extend(encode_to_vec((b"key".to_vec(), compressed), config).unwrap());
+        bytes.extend(encode_to_vec(u64::MAX, config).unwrap());
+
+        assert!(decoding_error(&bytes).starts_with("Uncompressed value length"));
+    }
+
+    #[test]
+    fn all_sections_round_trip() {
+        let value = vec![b'a'; 4 * COMPRESSION_THRESHOLD];
+        let bytes = Item::new(b"key", &value)
+            .with_checksum()
+            .with_expiration(1_700_000_000_000)
+            .with_header(b"content-type", b"text/plain")
+            .with_header(b"encoding", b"ascii")
+            .as_bytes()
+            .unwrap();
+        assert_eq!(bytes[PREFIX_LENGTH - 1], KNOWN_FLAGS);
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.get_key(), b"key");
+        assert_eq!(item.value, value);
+        assert!(item.checksum);
+        assert_eq!(item.expires_at(), Some(1_700_000_000_000));
+        assert_eq!(
+            item.headers(),
+            &[
+                (b"content-type".to_vec(), b"text/plain".to_vec()),
+                (b"encoding".to_vec(), b"ascii".to_vec()),
+            ]
+        );
+        assert_eq!(item.as_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn legacy_encoding_decodes() {
+        let bytes = encode_to_vec(
+            (b"key".to_vec(), b"value".to_vec()),
+            bincode::config::standard(),
+        )
+        .unwrap();
+        assert_eq!(Item::format_version(&bytes), LEGACY_FORMAT_VERSION);
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.get_key(), b"key");
+        assert_eq!(item.value, b"value");
+    }
+
+    #[test]
+    fn hand_built_versioned_encoding_decodes() {
+        let mut bytes = vec![FORMAT_MARKER, 1, 0];
+        bytes.extend(
+            encode_to_vec(
+                (b"key".to_vec(), b"value".to_vec()),
+                bincode::config::standard(),
+            )
+            .unwrap(),
+        );
+
+        let item = Item::from_bytes(&bytes).unwrap();
+        assert_eq!(item.get_key(), b"key");
+        assert_eq!(item.value, b"value");
+    }
+
+    #[test]
+    fn migrate_rewrites_legacy_encoding_only() {
+        let legacy = encode_to_vec(
+            (b"key".to_vec(), b"value".to_vec()),
+            bincode::config::standard(),
+        )
+        .unwrap();
+
+        let migrated = Item::migrate(&legacy).unwrap().unwrap();
+        assert_eq!(Item::format_version(&migrated), CURRENT_FORMAT_VERSION);
+        assert_eq!(Item::migrate(&migrated).unwrap(), None);
+    }
+}
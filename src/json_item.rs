@@ -0,0 +1,125 @@
+//! JSON item module provides a key-value record serialized as JSON, for interop with non-Rust tooling.
+//!
+//! The codec is chosen by the record type used with the cabinet: records written as
+//! [`JsonItem`] must be read back as [`JsonItem`]. JSON and bincode ([`Item`]) encodings
+//! are not cross-compatible.
+//!
+//! Keys and values are arbitrary bytes, encoded as standard base64 strings, e.g.
+//! `{"key":"a2V5","value":"dmFsdWU="}`.
+//!
+//! [`Item`]: crate::item::Item
+
+use std::fmt::{Debug, Formatter};
+use toolbox::backend::errors::BackendError;
+use toolbox::backend::record::Record;
+
+/// Represents a key-value pair item stored in the cabinet as JSON.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct JsonItem {
+    #[serde(with = "base64_bytes")]
+    key: Vec<u8>,
+    #[serde(with = "base64_bytes")]
+    pub value: Vec<u8>,
+}
+
+/// Serde adapter encoding byte fields as base64 strings
+mod base64_bytes {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Debug for JsonItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "key: {} => value : {}",
+            String::from_utf8_lossy(&self.key),
+            String::from_utf8_lossy(&self.value)
+        )
+    }
+}
+
+impl JsonItem {
+    /// Creates a new JsonItem with the given key and value.
+    ///
+    /// # Parameters
+    /// * `key` - Byte slice containing the key
+    /// * `value` - Byte slice containing the value
+    ///
+    /// # Returns
+    /// A new JsonItem instance
+    pub fn new(key: &[u8], value: &[u8]) -> JsonItem {
+        JsonItem {
+            key: key.to_vec(),
+            value: value.to_vec(),
+        }
+    }
+}
+
+impl Record for JsonItem {
+    /// Serializes this item into JSON bytes.
+    ///
+    /// # Returns
+    /// Serialized bytes of this item
+    fn as_bytes(&self) -> Result<Vec<u8>, BackendError> {
+        serde_json::to_vec(self).map_err(|err| BackendError::SerialiazationError(err.to_string()))
+    }
+
+    /// Creates a JsonItem from JSON bytes.
+    ///
+    /// # Parameters
+    /// * `bytes` - Serialized bytes of a JsonItem
+    ///
+    /// # Returns
+    /// Deserialized JsonItem
+    fn from_bytes(bytes: &[u8]) -> Result<JsonItem, BackendError> {
+        serde_json::from_slice(bytes)
+            .map_err(|err| BackendError::DeserializationError(err.to_string()))
+    }
+
+    /// Gets the key of this item.
+    ///
+    /// # Returns
+    /// A reference to the key bytes
+    fn get_key(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let value = [0, 159, 255];
+        let bytes = JsonItem::new(b"key", &value).as_bytes().unwrap();
+
+        let item = JsonItem::from_bytes(&bytes).unwrap();
+        assert_eq!(item.get_key(), b"key");
+        assert_eq!(item.value, value);
+    }
+
+    #[test]
+    fn bytes_are_base64_strings() {
+        let bytes = JsonItem::new(b"key", b"value").as_bytes().unwrap();
+
+        assert_eq!(bytes, br#"{"key":"a2V5","value":"dmFsdWU="}"#);
+    }
+
+    #[test]
+    fn invalid_base64_is_rejected() {
+        let result = JsonItem::from_bytes(br#"{"key":"a2V5","value":"not base64!"}"#);
+
+        assert!(matches!(result, Err(BackendError::DeserializationError(_))));
+    }
+}
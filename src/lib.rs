@@ -7,3 +7,4 @@ pub use toolbox::foundationdb;
 
 pub mod errors;
 pub mod item;
+pub mod json_item;
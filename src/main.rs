@@ -1,8 +1,40 @@
 use cabinet::errors::CabinetError;
 use cabinet::item::Item;
+use clap::Parser;
+use std::fs::File;
+use std::io;
 use toolbox::foundationdb::{Database, FdbBindingError};
 use toolbox::{with_tenant, with_transaction};
 
+/// Command line arguments of the cabinet binary
+#[derive(Debug, Parser)]
+struct Args {
+    /// Path to the FoundationDB cluster file, takes precedence over FDB_CLUSTER_PATH
+    #[arg(long, env = "FDB_CLUSTER_PATH")]
+    cluster_file: Option<String>,
+}
+
+/// Checks that the cluster file exists and is readable before connecting to it
+fn check_cluster_file(path: &str) -> Result<(), CabinetError> {
+    let to_error = |source| CabinetError::ClusterFile {
+        path: path.to_string(),
+        source,
+    };
+
+    let metadata = File::open(path)
+        .and_then(|file| file.metadata())
+        .map_err(to_error)?;
+
+    if !metadata.is_file() {
+        return Err(to_error(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a regular file",
+        )));
+    }
+
+    Ok(())
+}
+
 async fn cleanup(database: &Database) -> Result<(), FdbBindingError> {
     with_transaction(database, |trx| async move {
         trx.clear_range(b"\0", b"\xff");
@@ -13,11 +45,19 @@ async fn cleanup(database: &Database) -> Result<(), FdbBindingError> {
 
 #[tokio::main]
 async fn main() -> Result<(), CabinetError> {
-    let _guard = toolbox::get_network_thread()?;
+    let args = Args::parse();
 
-    let fdb_cluster_path = std::env::var("FDB_CLUSTER_PATH".to_string()).ok();
+    match &args.cluster_file {
+        Some(path) => {
+            check_cluster_file(path)?;
+            println!("Using cluster file {path}");
+        }
+        None => println!("Using default cluster file"),
+    }
 
-    let database = Database::new_compat(fdb_cluster_path.as_deref())
+    let _guard = toolbox::get_network_thread()?;
+
+    let database = Database::new_compat(args.cluster_file.as_deref())
         .await
         .expect("Failed to create database");
     cleanup(&database).await?;
@@ -108,3 +148,88 @@ async fn main() -> Result<(), CabinetError> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Serializes the tests reading or writing FDB_CLUSTER_PATH
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn parse_with_env(env: Option<&str>, args: &[&str]) -> Args {
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+
+        // SAFETY: every test touching the environment holds ENV_LOCK
+        unsafe {
+            match env {
+                Some(path) => std::env::set_var("FDB_CLUSTER_PATH", path),
+                None => std::env::remove_var("FDB_CLUSTER_PATH"),
+            }
+        }
+
+        let parsed = Args::try_parse_from(args);
+
+        // SAFETY: every test touching the environment holds ENV_LOCK
+        unsafe { std::env::remove_var("FDB_CLUSTER_PATH") };
+
+        parsed.unwrap()
+    }
+
+    #[test]
+    fn cluster_file_from_command_line() {
+        let args = parse_with_env(None, &["cabinet", "--cluster-file", "cli.cluster"]);
+        assert_eq!(args.cluster_file.as_deref(), Some("cli.cluster"));
+    }
+
+    #[test]
+    fn cluster_file_from_environment() {
+        let args = parse_with_env(Some("env.cluster"), &["cabinet"]);
+        assert_eq!(args.cluster_file.as_deref(), Some("env.cluster"));
+    }
+
+    #[test]
+    fn command_line_takes_precedence_over_environment() {
+        let args = parse_with_env(
+            Some("env.cluster"),
+            &["cabinet", "--cluster-file", "cli.cluster"],
+        );
+        assert_eq!(args.cluster_file.as_deref(), Some("cli.cluster"));
+    }
+
+    #[test]
+    fn default_cluster_file() {
+        let args = parse_with_env(None, &["cabinet"]);
+        assert_eq!(args.cluster_file, None);
+    }
+
+    #[test]
+    fn missing_cluster_file_is_rejected() {
+        let path = std::env::temp_dir().join("cabinet-missing.cluster");
+        let path = path.to_str().unwrap();
+
+        match check_cluster_file(path) {
+            Err(CabinetError::ClusterFile {
+                path: error_path,
+                source,
+            }) => {
+                assert_eq!(error_path, path);
+                assert_eq!(source.kind(), io::ErrorKind::NotFound);
+            }
+            other => panic!("Expected a cluster file error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn directory_cluster_file_is_rejected() {
+        let path = std::env::temp_dir();
+        let path = path.to_str().unwrap();
+
+        match check_cluster_file(path) {
+            Err(CabinetError::ClusterFile { source, .. }) => {
+                assert_eq!(source.kind(), io::ErrorKind::InvalidInput);
+            }
+            other => panic!("Expected a cluster file error, got {other:?}"),
+        }
+    }
+}